@@ -0,0 +1,8 @@
+//! GoMaluum Authentication Service core
+//!
+//! I/O-free parsing and data model for i-Ma'luum portal responses. This crate
+//! performs no networking or filesystem access and has no dependencies, so it
+//! compiles to `wasm32-unknown-unknown` and lets the web client parse portal
+//! HTML locally with the same parsers the server uses.
+
+pub mod login;
@@ -0,0 +1,127 @@
+//! Login form and CAS login response parsing
+//!
+//! This module builds the CAS login form and classifies the CAS response to a
+//! credential submission. It only works on already-fetched data (status code,
+//! headers and body), leaving the HTTP round trips to the caller.
+
+use std::fmt;
+
+/// Cookie name for MOD_AUTH_CAS authentication token
+pub const AUTH_COOKIE_NAME: &str = "MOD_AUTH_CAS";
+
+/// Phrases in the CAS login page indicating the credentials were rejected
+const LOGIN_FAILURE_MARKERS: [&str; 2] = ["Login failed", "Invalid credentials"];
+
+/// Reasons a CAS login response is rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginRejection {
+    /// The response did not redirect back to i-Ma'luum
+    MissingRedirect,
+    /// The login page reported invalid credentials
+    InvalidCredentials,
+    /// The response had a non-success, non-redirect status code
+    UnexpectedStatus(u16),
+}
+
+impl fmt::Display for LoginRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRedirect => write!(f, "no redirect location in login response"),
+            Self::InvalidCredentials => write!(f, "invalid credentials detected in response"),
+            Self::UnexpectedStatus(status) => write!(f, "unexpected status code {}", status),
+        }
+    }
+}
+
+impl std::error::Error for LoginRejection {}
+
+/// Builds the CAS login form fields for the given credentials
+pub fn login_form(username: &str, password: &str) -> [(&'static str, String); 5] {
+    [
+        ("username", username.to_string()),
+        ("password", password.to_string()),
+        ("execution", "e1s1".to_string()),
+        ("_eventId", "submit".to_string()),
+        ("geolocation", String::new()),
+    ]
+}
+
+/// Classifies the CAS response to a credential submission
+///
+/// # Arguments
+/// * `status` - HTTP status code of the response
+/// * `location` - Value of the `Location` header, if any
+/// * `body` - Response body
+///
+/// # Returns
+/// * `Ok(location)` - Login accepted, returns the redirect location carrying the ticket
+/// * `Err(LoginRejection)` - Login rejected
+pub fn parse_login_response(
+    status: u16,
+    location: Option<&str>,
+    body: &str,
+) -> Result<String, LoginRejection> {
+    let location = location.ok_or(LoginRejection::MissingRedirect)?;
+
+    if LOGIN_FAILURE_MARKERS
+        .iter()
+        .any(|marker| body.contains(marker))
+    {
+        return Err(LoginRejection::InvalidCredentials);
+    }
+
+    if !(200..400).contains(&status) {
+        return Err(LoginRejection::UnexpectedStatus(status));
+    }
+
+    Ok(location.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_form() {
+        let form = login_form("testuser", "testpass");
+
+        assert_eq!(form[0], ("username", "testuser".to_string()));
+        assert_eq!(form[1], ("password", "testpass".to_string()));
+        assert_eq!(form[2], ("execution", "e1s1".to_string()));
+        assert_eq!(form[3], ("_eventId", "submit".to_string()));
+        assert_eq!(form[4], ("geolocation", String::new()));
+    }
+
+    #[test]
+    fn test_parse_login_response_success() {
+        let location = "https://imaluum.iium.edu.my/home?ticket=ST-1";
+        assert_eq!(
+            parse_login_response(302, Some(location), ""),
+            Ok(location.to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_login_response_missing_redirect() {
+        assert_eq!(
+            parse_login_response(200, None, "<html></html>"),
+            Err(LoginRejection::MissingRedirect)
+        );
+    }
+
+    #[test]
+    fn test_parse_login_response_invalid_credentials() {
+        assert_eq!(
+            parse_login_response(302, Some("/cas/login"), "<p>Invalid credentials.</p>"),
+            Err(LoginRejection::InvalidCredentials)
+        );
+    }
+
+    #[test]
+    fn test_parse_login_response_error_status() {
+        assert_eq!(
+            parse_login_response(500, Some("/cas/login"), ""),
+            Err(LoginRejection::UnexpectedStatus(500))
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Audit logging with per-RPC and per-app sampling
+//!
+//! Every gRPC call passes through the `AuditLayer`, which writes an audit
+//! record to the `audit` log target. How much is recorded is decided by the
+//! `GAS_AUDIT_RULES` policy, so high-frequency probes do not flood the audit
+//! sink while important RPCs such as Login keep a complete record.
+//!
+//! Rules are comma-separated `selector=level` pairs:
+//!
+//! - Selectors: `app:<id>` (matched against the `x-app-id` metadata),
+//!   `<package.Service>/<Method>`, `<package.Service>/*`, or `*`
+//! - Levels: `full`, `summary`, `none`, or `sample:<n>` (a full record for
+//!   one call in every `n`)
+//!
+//! When several RPC rules match, the most specific wins: exact RPC, then
+//! service wildcard, then `*`. Calls matching no rule are recorded in full.
+//! The app id is supplied by the caller and not authenticated, so an `app:`
+//! rule can only raise the level chosen by the RPC rules, never lower it;
+//! otherwise any client could switch off auditing of its own calls.
+//! Request and response bodies are never recorded.
+
+use log::info;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::codegen::{BoxFuture, Service, http};
+use tonic::transport::server::TcpConnectInfo;
+use tower::Layer;
+
+use crate::config::{self, ConfigError, ConfigResult};
+use crate::metadata::{APP_METADATA_KEY, TENANT_METADATA_KEY};
+
+/// Default policy: health checks are not audited, everything else is in full
+pub const DEFAULT_AUDIT_RULES: &str = "grpc.health.v1.Health/*=none,*=full";
+
+/// How much of a call is recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditLevel {
+    /// No record
+    None,
+    /// RPC, status and latency only
+    Summary,
+    /// Summary plus app, tenant, peer and user agent
+    Full,
+    /// A full record for one call in every `n`
+    Sample(u64),
+}
+
+impl AuditLevel {
+    /// Ranks levels by how much of the call stream they record
+    ///
+    /// Summary records every call, so it ranks above any sampling rate.
+    fn verbosity(self) -> (u8, u64) {
+        match self {
+            Self::None => (0, 0),
+            Self::Sample(n) => (1, u64::MAX - n),
+            Self::Summary => (2, 0),
+            Self::Full => (3, 0),
+        }
+    }
+}
+
+/// Which calls a rule applies to, in increasing order of specificity
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Selector {
+    Any,
+    Service(String),
+    Method(String),
+    App(String),
+}
+
+impl Selector {
+    fn matches(&self, path: &str, app: Option<&str>) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Service(service) => path
+                .rsplit_once('/')
+                .is_some_and(|(prefix, _)| prefix == service),
+            Self::Method(method) => path == method,
+            Self::App(id) => app == Some(id.as_str()),
+        }
+    }
+}
+
+/// Audit verbosity policy for RPCs and apps
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditPolicy {
+    rules: Vec<(Selector, AuditLevel)>,
+}
+
+impl Default for AuditPolicy {
+    fn default() -> Self {
+        Self::parse("GAS_AUDIT_RULES", DEFAULT_AUDIT_RULES)
+            .expect("Default audit rules must be valid")
+    }
+}
+
+impl AuditPolicy {
+    /// Parses an audit policy such as `"grpc.gas.auth.Auth/Login=full,*=sample:10"`
+    pub fn parse(key: &'static str, value: &str) -> ConfigResult<Self> {
+        let invalid = || ConfigError::InvalidAuditRule {
+            key,
+            value: value.to_string(),
+        };
+
+        let mut rules = Vec::new();
+        for rule in value.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let (selector, level) = rule.split_once('=').ok_or_else(invalid)?;
+
+            let selector = match selector.trim().trim_start_matches('/') {
+                "*" => Selector::Any,
+                s if s.starts_with("app:") && s.len() > 4 => Selector::App(s[4..].to_string()),
+                s => match s.split_once('/') {
+                    Some((service, "*")) if !service.is_empty() => {
+                        Selector::Service(format!("/{}", service))
+                    }
+                    Some((service, method)) if !service.is_empty() && !method.is_empty() => {
+                        Selector::Method(format!("/{}", s))
+                    }
+                    _ => return Err(invalid()),
+                },
+            };
+
+            let level = match level.trim().to_ascii_lowercase().as_str() {
+                "full" => AuditLevel::Full,
+                "summary" => AuditLevel::Summary,
+                "none" => AuditLevel::None,
+                l => match l.strip_prefix("sample:").map(str::parse) {
+                    Some(Ok(n)) if n > 0 => AuditLevel::Sample(n),
+                    _ => return Err(invalid()),
+                },
+            };
+
+            rules.push((selector, level));
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Finds the rule deciding how a call is recorded
+    ///
+    /// The most specific matching RPC rule applies, unless a matching `app:`
+    /// rule is more verbose. Returns the rule's index, used to keep a sampling
+    /// counter per rule.
+    fn resolve(&self, path: &str, app: Option<&str>) -> Option<(usize, AuditLevel)> {
+        let most_specific = |app_rules: bool| {
+            self.rules
+                .iter()
+                .enumerate()
+                .filter(|(_, (selector, _))| {
+                    matches!(selector, Selector::App(_)) == app_rules && selector.matches(path, app)
+                })
+                .max_by(|(a, (sa, _)), (b, (sb, _))| sa.cmp(sb).then(b.cmp(a)))
+                .map(|(index, (_, level))| (index, *level))
+        };
+
+        // Calls matching no RPC rule are recorded in full, which no app rule
+        // can lower
+        let rpc_rule = most_specific(false)?;
+        match most_specific(true) {
+            Some(app_rule) if app_rule.1.verbosity() > rpc_rule.1.verbosity() => Some(app_rule),
+            _ => Some(rpc_rule),
+        }
+    }
+}
+
+/// Tower layer writing audit records for every gRPC call
+#[derive(Clone)]
+pub struct AuditLayer {
+    policy: Arc<AuditPolicy>,
+    counters: Arc<[AtomicU64]>,
+}
+
+impl AuditLayer {
+    /// Creates an audit layer enforcing `policy`
+    pub fn new(policy: AuditPolicy) -> Self {
+        let counters = policy.rules.iter().map(|_| AtomicU64::new(0)).collect();
+        Self {
+            policy: Arc::new(policy),
+            counters,
+        }
+    }
+
+    /// Decides whether and how a call is recorded, applying sampling
+    fn decide(&self, path: &str, app: Option<&str>) -> AuditLevel {
+        match self.policy.resolve(path, app) {
+            Some((index, AuditLevel::Sample(n))) => {
+                if self.counters[index]
+                    .fetch_add(1, Ordering::Relaxed)
+                    .is_multiple_of(n)
+                {
+                    AuditLevel::Full
+                } else {
+                    AuditLevel::None
+                }
+            }
+            Some((_, level)) => level,
+            None => AuditLevel::Full,
+        }
+    }
+}
+
+impl Default for AuditLayer {
+    fn default() -> Self {
+        Self::new(config::get().audit.clone())
+    }
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by `AuditLayer`
+#[derive(Clone)]
+pub struct AuditService<S> {
+    inner: S,
+    layer: AuditLayer,
+}
+
+/// Returns a metadata value as a string, or `-` when missing
+#[inline]
+fn header_or_dash(headers: &http::HeaderMap, name: &str) -> String {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-")
+        .to_string()
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for AuditService<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let path = req.uri().path().to_string();
+        let headers = req.headers();
+        let app = headers.get(APP_METADATA_KEY).and_then(|v| v.to_str().ok());
+
+        let level = self.layer.decide(&path, app);
+        if level == AuditLevel::None {
+            return Box::pin(self.inner.call(req));
+        }
+
+        // Only collected for full records
+        let details = (level == AuditLevel::Full).then(|| {
+            let peer = req
+                .extensions()
+                .get::<TcpConnectInfo>()
+                .and_then(|info| info.remote_addr())
+                .map_or_else(|| "-".to_string(), |addr| addr.to_string());
+            format!(
+                " app={} tenant={} peer={} user_agent={}",
+                header_or_dash(headers, APP_METADATA_KEY),
+                header_or_dash(headers, TENANT_METADATA_KEY),
+                peer,
+                header_or_dash(headers, "user-agent"),
+            )
+        });
+
+        let start = Instant::now();
+        let future = self.inner.call(req);
+
+        Box::pin(async move {
+            let result = future.await;
+
+            // Errors are returned as trailers-only responses, so their status
+            // is in the headers; successful calls carry it in the trailers
+            let status = match &result {
+                Ok(response) => response
+                    .headers()
+                    .get("grpc-status")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("0")
+                    .to_string(),
+                Err(_) => "transport-error".to_string(),
+            };
+
+            info!(
+                target: "audit",
+                "rpc={} status={} latency_ms={}{}",
+                path,
+                status,
+                start.elapsed().as_millis(),
+                details.unwrap_or_default()
+            );
+
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOGIN: &str = "/grpc.gas.auth.Auth/Login";
+    const ECHO: &str = "/grpc.gas.unaryecho.Echo/UnaryEcho";
+    const HEALTH: &str = "/grpc.health.v1.Health/Check";
+
+    #[test]
+    fn test_default_policy() {
+        let layer = AuditLayer::new(AuditPolicy::default());
+
+        assert_eq!(layer.decide(HEALTH, None), AuditLevel::None);
+        assert_eq!(layer.decide(LOGIN, None), AuditLevel::Full);
+    }
+
+    #[test]
+    fn test_policy_per_rpc() {
+        let policy = AuditPolicy::parse(
+            "GAS_AUDIT_RULES",
+            "grpc.gas.auth.Auth/Login=full, grpc.gas.unaryecho.Echo/*=summary, *=none",
+        )
+        .unwrap();
+        let layer = AuditLayer::new(policy);
+
+        assert_eq!(layer.decide(LOGIN, None), AuditLevel::Full);
+        assert_eq!(layer.decide(ECHO, None), AuditLevel::Summary);
+        assert_eq!(layer.decide(HEALTH, None), AuditLevel::None);
+    }
+
+    #[test]
+    fn test_policy_app_rule_raises_level() {
+        let policy = AuditPolicy::parse(
+            "GAS_AUDIT_RULES",
+            "app:admin-console=full,grpc.gas.unaryecho.Echo/*=summary,*=full",
+        )
+        .unwrap();
+        let layer = AuditLayer::new(policy);
+
+        assert_eq!(layer.decide(ECHO, Some("admin-console")), AuditLevel::Full);
+        assert_eq!(layer.decide(ECHO, Some("web")), AuditLevel::Summary);
+    }
+
+    #[test]
+    fn test_policy_app_rule_cannot_lower_level() {
+        let policy = AuditPolicy::parse(
+            "GAS_AUDIT_RULES",
+            "app:uptime-probe=none,app:web=sample:10,grpc.gas.unaryecho.Echo/*=summary",
+        )
+        .unwrap();
+        let layer = AuditLayer::new(policy);
+
+        // x-app-id is caller-supplied, so it must not switch auditing off
+        assert_eq!(
+            layer.decide(ECHO, Some("uptime-probe")),
+            AuditLevel::Summary
+        );
+        assert_eq!(layer.decide(ECHO, Some("web")), AuditLevel::Summary);
+        assert_eq!(layer.decide(LOGIN, Some("uptime-probe")), AuditLevel::Full);
+    }
+
+    #[test]
+    fn test_policy_sampling() {
+        let policy =
+            AuditPolicy::parse("GAS_AUDIT_RULES", "grpc.gas.unaryecho.Echo/*=sample:3").unwrap();
+        let layer = AuditLayer::new(policy);
+
+        let recorded = (0..9)
+            .filter(|_| layer.decide(ECHO, None) == AuditLevel::Full)
+            .count();
+        assert_eq!(recorded, 3);
+    }
+
+    #[test]
+    fn test_policy_invalid() {
+        for rules in ["Login", "*=loud", "*=sample:0", "app:=none", "Auth/=full"] {
+            assert_eq!(
+                AuditPolicy::parse("GAS_AUDIT_RULES", rules),
+                Err(ConfigError::InvalidAuditRule {
+                    key: "GAS_AUDIT_RULES",
+                    value: rules.to_string(),
+                })
+            );
+        }
+    }
+}
@@ -1,4 +1,13 @@
 //! Constants module for i-Ma'luum authentication URLs and configuration
+//!
+//! These are the live endpoints. The URLs actually used are built from them by
+//! `config::UpstreamConfig`, which can point them at a mock upstream instead.
+
+/// Domain shared by all live IIUM hosts
+pub const IIUM_DOMAIN: &str = "iium.edu.my";
+
+/// i-Ma'luum root URL
+pub const IMALUUM_ROOT: &str = "https://imaluum.iium.edu.my";
 
 /// i-Ma'luum main page URL
 pub const IMALUUM_PAGE: &str = "https://imaluum.iium.edu.my/";
@@ -14,7 +23,4 @@ pub const IMALUUM_LOGIN_PAGE: &str = "https://cas.iium.edu.my:8448/cas/login?ser
 pub const CAS_ROOT: &str = "https://cas.iium.edu.my:8448";
 
 /// Cookie name for MOD_AUTH_CAS authentication token
-pub const AUTH_COOKIE_NAME: &str = "MOD_AUTH_CAS";
-
-/// Default timeout for HTTP requests (in seconds)
-pub const REQUEST_TIMEOUT_SECS: u64 = 10;
+pub use gas_core::login::AUTH_COOKIE_NAME;
@@ -18,6 +18,15 @@ pub enum AuthError {
     #[error("HTTP request failed: {0}")]
     RequestFailed(#[from] reqwest::Error),
 
+    #[error("Upstream response truncated: {0}")]
+    TruncatedResponse(String),
+
+    #[error("Upstream response too large: {size} bytes exceeds limit of {limit} bytes")]
+    ResponseTooLarge { size: usize, limit: usize },
+
+    #[error("Refusing to contact live host while GAS_ENV=test: {0}")]
+    LiveUpstreamBlocked(String),
+
     #[error("Failed to close request body")]
     FailedToCloseRequestBody,
 
@@ -36,6 +45,9 @@ pub enum AuthError {
     #[error("Network timeout")]
     NetworkTimeout,
 
+    #[error("Authentication server unavailable: circuit breaker open")]
+    CircuitOpen,
+
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
@@ -51,7 +63,10 @@ impl From<AuthError> for Status {
                 Status::invalid_argument(error.to_string())
             }
             AuthError::NetworkTimeout => Status::deadline_exceeded(error.to_string()),
-            AuthError::RequestFailed(_) => Status::unavailable(error.to_string()),
+            AuthError::LiveUpstreamBlocked(_) => Status::failed_precondition(error.to_string()),
+            AuthError::RequestFailed(_)
+            | AuthError::TruncatedResponse(_)
+            | AuthError::CircuitOpen => Status::unavailable(error.to_string()),
             _ => Status::internal(error.to_string()),
         }
     }
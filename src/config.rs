@@ -0,0 +1,686 @@
+//! Configuration module for timeouts, intervals, size limits and audit rules
+//!
+//! Every tunable is read from the environment and accepts human-friendly
+//! values such as `"30s"`, `"5m"` or `"10MB"`. Invalid values, including zero
+//! timeouts, intervals and sizes, are rejected at startup with an error naming
+//! the offending key.
+
+use once_cell::sync::OnceCell;
+use std::env;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+use crate::audit::AuditPolicy;
+use crate::auth::constants::{
+    CAS_ROOT, IIUM_DOMAIN, IMALUUM_CAS_PAGE, IMALUUM_LOGIN_PAGE, IMALUUM_PAGE, IMALUUM_ROOT,
+};
+
+/// Default mock upstream used for both i-Ma'luum and CAS when `GAS_ENV=test`
+pub const MOCK_UPSTREAM_URL: &str = "http://127.0.0.1:8448";
+
+/// Global configuration, set once at startup
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Configuration error types
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigError {
+    #[error(
+        "Invalid duration for {key}: '{value}' (expected a non-zero duration, e.g. \"500ms\", \"30s\", \"5m\")"
+    )]
+    InvalidDuration { key: &'static str, value: String },
+
+    #[error(
+        "Invalid size for {key}: '{value}' (expected a non-zero size, e.g. \"512KB\", \"10MB\")"
+    )]
+    InvalidSize { key: &'static str, value: String },
+
+    #[error("Invalid number for {key}: '{value}' (expected a positive integer)")]
+    InvalidNumber { key: &'static str, value: String },
+
+    #[error(
+        "Invalid audit rules for {key}: '{value}' (expected e.g. \"grpc.gas.auth.Auth/Login=full,*=sample:10\")"
+    )]
+    InvalidAuditRule { key: &'static str, value: String },
+
+    #[error("Invalid environment for {key}: '{value}' (expected \"production\" or \"test\")")]
+    InvalidEnvironment { key: &'static str, value: String },
+
+    #[error("Invalid URL for {key}: '{value}'")]
+    InvalidUrl { key: &'static str, value: String },
+
+    #[error("Refusing to use live IIUM host for {key}: '{value}' while GAS_ENV=test")]
+    LiveUpstreamInTest { key: &'static str, value: String },
+
+    #[error("Configuration already initialized")]
+    AlreadyInitialized,
+}
+
+/// Result type alias for configuration operations
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+/// Upstream HTTP client settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpConfig {
+    /// `GAS_HTTP_CONNECT_TIMEOUT` - TCP connect timeout
+    pub connect_timeout: Duration,
+    /// `GAS_HTTP_REQUEST_TIMEOUT` - Total timeout for a single request
+    pub request_timeout: Duration,
+    /// `GAS_HTTP_POOL_IDLE_TIMEOUT` - How long idle pooled connections are kept
+    pub pool_idle_timeout: Duration,
+    /// `GAS_HTTP_TCP_KEEPALIVE` - TCP keepalive interval
+    pub tcp_keepalive: Duration,
+    /// `GAS_HTTP_MAX_BODY_SIZE` - Largest upstream response body that will be read
+    pub max_body_size: usize,
+    /// `GAS_CAS_BREAKER_THRESHOLD` - Consecutive CAS failures before the circuit breaker opens
+    pub cas_breaker_threshold: usize,
+    /// `GAS_CAS_BREAKER_COOLDOWN` - How long the CAS circuit breaker stays open
+    pub cas_breaker_cooldown: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            // i-Ma'luum can be slow
+            connect_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(30),
+            pool_idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Duration::from_secs(60),
+            max_body_size: 2 * 1024 * 1024,
+            cas_breaker_threshold: 5,
+            cas_breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// gRPC server settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// `GAS_GRPC_MAX_MESSAGE_SIZE` - Maximum encoded or decoded gRPC message size
+    pub max_message_size: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            max_message_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Health reporting settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthConfig {
+    /// `GAS_HEALTH_CHECK_INTERVAL` - How often the serving status is re-evaluated
+    pub check_interval: Duration,
+    /// `GAS_HEALTH_MAX_IN_FLIGHT` - In-flight upstream logins above which the service reports NOT_SERVING
+    pub max_in_flight: usize,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(5),
+            max_in_flight: 100,
+        }
+    }
+}
+
+/// Deployment environment, from `GAS_ENV`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Environment {
+    #[default]
+    Production,
+    /// Live IIUM hosts are never contacted
+    Test,
+}
+
+impl Environment {
+    /// Returns true when live IIUM hosts must not be contacted
+    pub fn is_test(self) -> bool {
+        self == Self::Test
+    }
+}
+
+/// Upstream i-Ma'luum and CAS endpoints
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamConfig {
+    pub imaluum_page: String,
+    pub cas_page: String,
+    pub login_page: String,
+    pub cas_root: String,
+}
+
+impl UpstreamConfig {
+    /// The live i-Ma'luum and CAS endpoints
+    pub fn live() -> Self {
+        Self::with_origins(IMALUUM_ROOT, CAS_ROOT)
+    }
+
+    /// The live endpoints with their origins replaced, e.g. by a mock upstream
+    pub fn with_origins(imaluum_origin: &str, cas_origin: &str) -> Self {
+        let rebase =
+            |url: &str, live_origin: &str, origin: &str| match url.strip_prefix(live_origin) {
+                Some(rest) => format!("{}{}", origin.trim_end_matches('/'), rest),
+                None => url.to_string(),
+            };
+
+        Self {
+            imaluum_page: rebase(IMALUUM_PAGE, IMALUUM_ROOT, imaluum_origin),
+            cas_page: rebase(IMALUUM_CAS_PAGE, CAS_ROOT, cas_origin),
+            login_page: rebase(IMALUUM_LOGIN_PAGE, CAS_ROOT, cas_origin),
+            cas_root: rebase(CAS_ROOT, CAS_ROOT, cas_origin),
+        }
+    }
+
+    /// The mock upstream endpoints used when `GAS_ENV=test`
+    pub fn mock() -> Self {
+        Self::with_origins(MOCK_UPSTREAM_URL, MOCK_UPSTREAM_URL)
+    }
+}
+
+/// Service configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// `GAS_ENV` - Deployment environment
+    pub env: Environment,
+    /// `GAS_IMALUUM_URL`, `GAS_CAS_URL` - Upstream origins
+    pub upstream: UpstreamConfig,
+    pub http: HttpConfig,
+    pub server: ServerConfig,
+    pub health: HealthConfig,
+    /// `GAS_AUDIT_RULES` - Audit verbosity per RPC and per app
+    pub audit: AuditPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            env: Environment::default(),
+            upstream: UpstreamConfig::live(),
+            http: HttpConfig::default(),
+            server: ServerConfig::default(),
+            health: HealthConfig::default(),
+            audit: AuditPolicy::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from environment variables, falling back to
+    /// defaults for unset keys
+    pub fn from_env() -> ConfigResult<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Loads the configuration using `lookup` to resolve each key
+    pub fn from_lookup<F>(lookup: F) -> ConfigResult<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let defaults = Self::default();
+        let duration = |key, default| match lookup(key) {
+            Some(value) => parse_nonzero_duration(key, &value),
+            None => Ok(default),
+        };
+        let size = |key, default| match lookup(key) {
+            Some(value) => parse_nonzero_size(key, &value),
+            None => Ok(default),
+        };
+        let number = |key, default| match lookup(key) {
+            Some(value) => parse_nonzero_number(key, &value),
+            None => Ok(default),
+        };
+
+        let env = match lookup("GAS_ENV") {
+            Some(value) => parse_environment("GAS_ENV", &value)?,
+            None => defaults.env,
+        };
+
+        // In the test environment unset origins fall back to the mock upstream,
+        // and configured origins must not point at live IIUM hosts
+        let origin = |key, live_origin| match lookup(key) {
+            Some(value) => {
+                if Url::parse(&value).is_err() {
+                    return Err(ConfigError::InvalidUrl { key, value });
+                }
+                if env.is_test() && is_live_url(&value) {
+                    return Err(ConfigError::LiveUpstreamInTest { key, value });
+                }
+                Ok(value)
+            }
+            None if env.is_test() => Ok(MOCK_UPSTREAM_URL.to_string()),
+            None => Ok(String::from(live_origin)),
+        };
+
+        Ok(Self {
+            env,
+            upstream: UpstreamConfig::with_origins(
+                &origin("GAS_IMALUUM_URL", IMALUUM_ROOT)?,
+                &origin("GAS_CAS_URL", CAS_ROOT)?,
+            ),
+            http: HttpConfig {
+                connect_timeout: duration(
+                    "GAS_HTTP_CONNECT_TIMEOUT",
+                    defaults.http.connect_timeout,
+                )?,
+                request_timeout: duration(
+                    "GAS_HTTP_REQUEST_TIMEOUT",
+                    defaults.http.request_timeout,
+                )?,
+                pool_idle_timeout: duration(
+                    "GAS_HTTP_POOL_IDLE_TIMEOUT",
+                    defaults.http.pool_idle_timeout,
+                )?,
+                tcp_keepalive: duration("GAS_HTTP_TCP_KEEPALIVE", defaults.http.tcp_keepalive)?,
+                max_body_size: size("GAS_HTTP_MAX_BODY_SIZE", defaults.http.max_body_size)?,
+                cas_breaker_threshold: number(
+                    "GAS_CAS_BREAKER_THRESHOLD",
+                    defaults.http.cas_breaker_threshold,
+                )?,
+                cas_breaker_cooldown: duration(
+                    "GAS_CAS_BREAKER_COOLDOWN",
+                    defaults.http.cas_breaker_cooldown,
+                )?,
+            },
+            server: ServerConfig {
+                max_message_size: size(
+                    "GAS_GRPC_MAX_MESSAGE_SIZE",
+                    defaults.server.max_message_size,
+                )?,
+            },
+            health: HealthConfig {
+                check_interval: duration(
+                    "GAS_HEALTH_CHECK_INTERVAL",
+                    defaults.health.check_interval,
+                )?,
+                max_in_flight: number("GAS_HEALTH_MAX_IN_FLIGHT", defaults.health.max_in_flight)?,
+            },
+            audit: match lookup("GAS_AUDIT_RULES") {
+                Some(value) => AuditPolicy::parse("GAS_AUDIT_RULES", &value)?,
+                None => defaults.audit,
+            },
+        })
+    }
+}
+
+/// Sets the global configuration
+///
+/// Must be called at most once, before the configuration is first read.
+pub fn init(config: Config) -> ConfigResult<()> {
+    CONFIG
+        .set(config)
+        .map_err(|_| ConfigError::AlreadyInitialized)
+}
+
+/// Returns the global configuration, loading it from the environment if
+/// `init` was never called
+///
+/// This is how `GAS_ENV=test cargo test` reaches code that reads the global
+/// configuration.
+///
+/// # Panics
+/// Panics if the environment holds an invalid configuration.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| Config::from_env().expect("Invalid configuration in environment"))
+}
+
+/// Returns true if `url` points at a live IIUM host
+///
+/// A fully-qualified host with a trailing dot resolves to the same host, so
+/// it is matched too.
+pub fn is_live_url(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+        .is_some_and(|host| {
+            let host = host.strip_suffix('.').unwrap_or(&host);
+            host == IIUM_DOMAIN || host.ends_with(&format!(".{}", IIUM_DOMAIN))
+        })
+}
+
+/// Parses a deployment environment name
+pub fn parse_environment(key: &'static str, value: &str) -> ConfigResult<Environment> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "production" | "prod" => Ok(Environment::Production),
+        "test" => Ok(Environment::Test),
+        _ => Err(ConfigError::InvalidEnvironment {
+            key,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Splits a value such as `"10MB"` into its number and unit
+#[inline]
+fn split_unit(value: &str) -> (&str, &str) {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    (number, unit.trim())
+}
+
+/// Parses a human-friendly duration such as `"500ms"`, `"30s"`, `"5m"` or `"1h"`
+///
+/// A bare number is interpreted as seconds.
+pub fn parse_duration(key: &'static str, value: &str) -> ConfigResult<Duration> {
+    let invalid = || ConfigError::InvalidDuration {
+        key,
+        value: value.to_string(),
+    };
+
+    let (number, unit) = split_unit(value);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+
+    let millis_per_unit = match unit.to_ascii_lowercase().as_str() {
+        "ms" => 1,
+        "" | "s" | "sec" | "secs" => 1_000,
+        "m" | "min" | "mins" => 60_000,
+        "h" | "hr" | "hrs" => 3_600_000,
+        _ => return Err(invalid()),
+    };
+
+    number
+        .checked_mul(millis_per_unit)
+        .map(Duration::from_millis)
+        .ok_or_else(invalid)
+}
+
+/// Parses a duration that must be greater than zero
+///
+/// Every configured timeout and interval needs this: a zero request timeout
+/// fails every login and a zero interval panics the timer.
+pub fn parse_nonzero_duration(key: &'static str, value: &str) -> ConfigResult<Duration> {
+    match parse_duration(key, value)? {
+        Duration::ZERO => Err(ConfigError::InvalidDuration {
+            key,
+            value: value.to_string(),
+        }),
+        duration => Ok(duration),
+    }
+}
+
+/// Parses a human-friendly size such as `"512B"`, `"64KB"` or `"10MB"`
+///
+/// Units are binary (1KB = 1024 bytes). A bare number is interpreted as bytes.
+pub fn parse_size(key: &'static str, value: &str) -> ConfigResult<usize> {
+    let invalid = || ConfigError::InvalidSize {
+        key,
+        value: value.to_string(),
+    };
+
+    let (number, unit) = split_unit(value);
+    let number: usize = number.parse().map_err(|_| invalid())?;
+
+    let bytes_per_unit: usize = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+
+    number.checked_mul(bytes_per_unit).ok_or_else(invalid)
+}
+
+/// Parses a size that must be greater than zero
+pub fn parse_nonzero_size(key: &'static str, value: &str) -> ConfigResult<usize> {
+    match parse_size(key, value)? {
+        0 => Err(ConfigError::InvalidSize {
+            key,
+            value: value.to_string(),
+        }),
+        size => Ok(size),
+    }
+}
+
+/// Parses a plain non-negative integer such as a count or threshold
+pub fn parse_number(key: &'static str, value: &str) -> ConfigResult<usize> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ConfigError::InvalidNumber {
+            key,
+            value: value.to_string(),
+        })
+}
+
+/// Parses an integer that must be greater than zero
+pub fn parse_nonzero_number(key: &'static str, value: &str) -> ConfigResult<usize> {
+    match parse_number(key, value)? {
+        0 => Err(ConfigError::InvalidNumber {
+            key,
+            value: value.to_string(),
+        }),
+        number => Ok(number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("KEY", "500ms"),
+            Ok(Duration::from_millis(500))
+        );
+        assert_eq!(parse_duration("KEY", "30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("KEY", "5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("KEY", "1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("KEY", "15"), Ok(Duration::from_secs(15)));
+        assert_eq!(parse_duration("KEY", " 2 m "), Ok(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert_eq!(
+            parse_duration("GAS_HTTP_REQUEST_TIMEOUT", "soon"),
+            Err(ConfigError::InvalidDuration {
+                key: "GAS_HTTP_REQUEST_TIMEOUT",
+                value: "soon".to_string(),
+            })
+        );
+        assert!(parse_duration("KEY", "10d").is_err());
+        assert!(parse_duration("KEY", "-5s").is_err());
+        assert!(parse_duration("KEY", "").is_err());
+    }
+
+    #[test]
+    fn test_parse_nonzero_duration() {
+        assert_eq!(
+            parse_nonzero_duration("KEY", "1ms"),
+            Ok(Duration::from_millis(1))
+        );
+        for value in ["0", "0s", "0ms"] {
+            assert_eq!(
+                parse_nonzero_duration("GAS_HTTP_REQUEST_TIMEOUT", value),
+                Err(ConfigError::InvalidDuration {
+                    key: "GAS_HTTP_REQUEST_TIMEOUT",
+                    value: value.to_string(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("KEY", "512"), Ok(512));
+        assert_eq!(parse_size("KEY", "512B"), Ok(512));
+        assert_eq!(parse_size("KEY", "64KB"), Ok(64 * 1024));
+        assert_eq!(parse_size("KEY", "10MB"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("KEY", "1gb"), Ok(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_size_invalid() {
+        let err = parse_size("GAS_GRPC_MAX_MESSAGE_SIZE", "10XB").unwrap_err();
+        assert!(err.to_string().contains("GAS_GRPC_MAX_MESSAGE_SIZE"));
+        assert!(parse_size("KEY", "1.5MB").is_err());
+    }
+
+    #[test]
+    fn test_parse_nonzero_size() {
+        assert_eq!(parse_nonzero_size("KEY", "1B"), Ok(1));
+        assert_eq!(
+            parse_nonzero_size("GAS_GRPC_MAX_MESSAGE_SIZE", "0MB"),
+            Err(ConfigError::InvalidSize {
+                key: "GAS_GRPC_MAX_MESSAGE_SIZE",
+                value: "0MB".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("KEY", " 42 "), Ok(42));
+        assert!(parse_number("KEY", "-1").is_err());
+        assert!(parse_number("KEY", "many").is_err());
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        assert_eq!(parse_environment("GAS_ENV", "test"), Ok(Environment::Test));
+        assert_eq!(
+            parse_environment("GAS_ENV", "Production"),
+            Ok(Environment::Production)
+        );
+        assert!(parse_environment("GAS_ENV", "tset").is_err());
+        assert!(parse_environment("GAS_ENV", "development").is_err());
+    }
+
+    #[test]
+    fn test_is_live_url() {
+        assert!(is_live_url("https://cas.iium.edu.my:8448/cas/login"));
+        assert!(is_live_url("https://IMALUUM.IIUM.EDU.MY/home"));
+        assert!(is_live_url("https://cas.iium.edu.my.:8448/cas/login"));
+        assert!(is_live_url("https://imaluum.iium.edu.my./home"));
+        assert!(!is_live_url("http://127.0.0.1:8448/cas/login"));
+        assert!(!is_live_url("https://notiium.edu.my.example.com"));
+        assert!(!is_live_url("/home"));
+    }
+
+    #[test]
+    fn test_live_upstream_matches_constants() {
+        let upstream = UpstreamConfig::live();
+        assert_eq!(upstream.imaluum_page, IMALUUM_PAGE);
+        assert_eq!(upstream.cas_page, IMALUUM_CAS_PAGE);
+        assert_eq!(upstream.login_page, IMALUUM_LOGIN_PAGE);
+        assert_eq!(upstream.cas_root, CAS_ROOT);
+    }
+
+    #[test]
+    fn test_defaults_to_production() {
+        let config = Config::from_lookup(|_| None).unwrap();
+        assert_eq!(config.env, Environment::Production);
+        assert_eq!(config.upstream, UpstreamConfig::live());
+    }
+
+    #[test]
+    fn test_test_environment_uses_mock_upstream() {
+        let config =
+            Config::from_lookup(|key| (key == "GAS_ENV").then(|| "test".to_string())).unwrap();
+        assert!(config.env.is_test());
+        assert!(!is_live_url(&config.upstream.cas_page));
+        assert!(config.upstream.login_page.starts_with(MOCK_UPSTREAM_URL));
+    }
+
+    #[test]
+    fn test_live_upstream_rejected_in_test() {
+        let values = HashMap::from([
+            ("GAS_ENV", "test"),
+            ("GAS_CAS_URL", "https://cas.iium.edu.my:8448"),
+        ]);
+        let err = Config::from_lookup(|key| values.get(key).map(|v| v.to_string())).unwrap_err();
+
+        assert_eq!(
+            err,
+            ConfigError::LiveUpstreamInTest {
+                key: "GAS_CAS_URL",
+                value: "https://cas.iium.edu.my:8448".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_defaults_when_unset() {
+        let config = Config::from_lookup(|_| None).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_config_from_lookup() {
+        let values = HashMap::from([
+            ("GAS_HTTP_REQUEST_TIMEOUT", "1m"),
+            ("GAS_GRPC_MAX_MESSAGE_SIZE", "8MB"),
+        ]);
+        let config = Config::from_lookup(|key| values.get(key).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.http.request_timeout, Duration::from_secs(60));
+        assert_eq!(config.http.connect_timeout, Duration::from_secs(10));
+        assert_eq!(config.server.max_message_size, 8 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_config_error_names_key() {
+        let err = Config::from_lookup(|key| {
+            (key == "GAS_HTTP_TCP_KEEPALIVE").then(|| "forever".to_string())
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ConfigError::InvalidDuration {
+                key: "GAS_HTTP_TCP_KEEPALIVE",
+                value: "forever".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_rejects_zero() {
+        for key in [
+            "GAS_HTTP_CONNECT_TIMEOUT",
+            "GAS_HTTP_REQUEST_TIMEOUT",
+            "GAS_HTTP_POOL_IDLE_TIMEOUT",
+            "GAS_HTTP_TCP_KEEPALIVE",
+            "GAS_CAS_BREAKER_COOLDOWN",
+            "GAS_HEALTH_CHECK_INTERVAL",
+        ] {
+            assert_eq!(
+                Config::from_lookup(|k| (k == key).then(|| "0s".to_string())),
+                Err(ConfigError::InvalidDuration {
+                    key,
+                    value: "0s".to_string(),
+                })
+            );
+        }
+
+        for key in ["GAS_HTTP_MAX_BODY_SIZE", "GAS_GRPC_MAX_MESSAGE_SIZE"] {
+            assert_eq!(
+                Config::from_lookup(|k| (k == key).then(|| "0".to_string())),
+                Err(ConfigError::InvalidSize {
+                    key,
+                    value: "0".to_string(),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_rejects_zero_counts() {
+        for key in ["GAS_CAS_BREAKER_THRESHOLD", "GAS_HEALTH_MAX_IN_FLIGHT"] {
+            assert_eq!(
+                Config::from_lookup(|k| (k == key).then(|| "0".to_string())),
+                Err(ConfigError::InvalidNumber {
+                    key,
+                    value: "0".to_string(),
+                })
+            );
+        }
+    }
+}
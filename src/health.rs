@@ -0,0 +1,105 @@
+//! Load-aware gRPC health reporting
+//!
+//! The standard `grpc.health.v1.Health` service is kept in sync with the
+//! service's load: it reports NOT_SERVING while too many upstream logins are
+//! in flight or the CAS circuit breaker is open, so the load balancer sheds
+//! traffic to healthier replicas before requests start timing out.
+
+use log::{info, warn};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tonic::server::NamedService;
+use tonic_health::ServingStatus;
+use tonic_health::server::HealthReporter;
+
+use crate::auth::grpc::GRPCServer;
+use crate::auth::grpc::auth_proto::auth_server::AuthServer;
+use crate::config;
+use crate::http::breaker::CAS_BREAKER;
+
+/// Number of upstream logins currently in flight
+static IN_FLIGHT_LOGINS: AtomicUsize = AtomicUsize::new(0);
+
+/// Tracks an in-flight upstream login for as long as it is alive
+pub struct InFlightGuard;
+
+impl InFlightGuard {
+    /// Marks a login as in flight until the guard is dropped
+    pub fn new() -> Self {
+        IN_FLIGHT_LOGINS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Default for InFlightGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT_LOGINS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns the number of upstream logins currently in flight
+pub fn in_flight_logins() -> usize {
+    IN_FLIGHT_LOGINS.load(Ordering::Relaxed)
+}
+
+/// Determines the serving status from the current load
+#[inline]
+fn serving_status(in_flight: usize, max_in_flight: usize, breaker_open: bool) -> ServingStatus {
+    if breaker_open || in_flight > max_in_flight {
+        ServingStatus::NotServing
+    } else {
+        ServingStatus::Serving
+    }
+}
+
+/// Periodically re-evaluates the load and updates the health service
+///
+/// Both the overall server status (`""`) and the Auth service status are
+/// updated, since load balancers usually probe the former.
+pub async fn report_load(reporter: HealthReporter) {
+    let health = &config::get().health;
+    let mut interval = tokio::time::interval(health.check_interval);
+    let mut last_status = None;
+
+    loop {
+        interval.tick().await;
+
+        let in_flight = in_flight_logins();
+        let breaker_open = CAS_BREAKER.is_open();
+        let status = serving_status(in_flight, health.max_in_flight, breaker_open);
+
+        if last_status != Some(status) {
+            match status {
+                ServingStatus::Serving => info!("Health status: SERVING"),
+                _ => warn!(
+                    "Health status: NOT_SERVING (in-flight logins: {}, CAS breaker open: {})",
+                    in_flight, breaker_open
+                ),
+            }
+            last_status = Some(status);
+        }
+
+        reporter.set_service_status("", status).await;
+        reporter
+            .set_service_status(<AuthServer<GRPCServer> as NamedService>::NAME, status)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serving_status() {
+        assert_eq!(serving_status(0, 10, false), ServingStatus::Serving);
+        assert_eq!(serving_status(10, 10, false), ServingStatus::Serving);
+        assert_eq!(serving_status(11, 10, false), ServingStatus::NotServing);
+        assert_eq!(serving_status(0, 10, true), ServingStatus::NotServing);
+    }
+}
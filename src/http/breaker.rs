@@ -0,0 +1,107 @@
+//! Circuit breaker for upstream CAS requests
+//!
+//! After a run of consecutive network failures the breaker opens and logins
+//! fail fast instead of piling up behind an unreachable CAS. Once the cooldown
+//! has elapsed requests are let through again; the first success closes the
+//! breaker and another failure re-opens it.
+
+use log::warn;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+/// Global circuit breaker guarding requests to CAS
+pub static CAS_BREAKER: Lazy<CircuitBreaker> = Lazy::new(|| {
+    let http = &config::get().http;
+    CircuitBreaker::new(http.cas_breaker_threshold, http.cas_breaker_cooldown)
+});
+
+/// Consecutive-failure circuit breaker
+pub struct CircuitBreaker {
+    failure_threshold: usize,
+    cooldown: Duration,
+    consecutive_failures: AtomicUsize,
+    opened_at: Mutex<Option<Instant>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker that opens after `failure_threshold`
+    /// consecutive failures and stays open for `cooldown`
+    pub fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            consecutive_failures: AtomicUsize::new(0),
+            opened_at: Mutex::new(None),
+        }
+    }
+
+    /// Returns true while the breaker is open and requests should be rejected
+    pub fn is_open(&self) -> bool {
+        match *self.opened_at.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some(opened_at) => opened_at.elapsed() < self.cooldown,
+            None => false,
+        }
+    }
+
+    /// Records a request that reached the upstream, closing the breaker
+    pub fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self.opened_at.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Records a request that failed to reach the upstream
+    pub fn record_failure(&self) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if failures >= self.failure_threshold {
+            let mut opened_at = self.opened_at.lock().unwrap_or_else(|e| e.into_inner());
+            if opened_at.is_none() {
+                warn!(
+                    "CAS circuit breaker opened after {} consecutive failures",
+                    failures
+                );
+            }
+            *opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+
+        breaker.record_failure();
+        assert!(breaker.is_open());
+    }
+
+    #[test]
+    fn test_success_closes_breaker() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+
+        breaker.record_failure();
+        assert!(breaker.is_open());
+
+        breaker.record_success();
+        assert!(!breaker.is_open());
+    }
+
+    #[test]
+    fn test_breaker_half_opens_after_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+        breaker.record_failure();
+        assert!(!breaker.is_open());
+    }
+}
@@ -5,27 +5,30 @@
 
 use once_cell::sync::Lazy;
 use reqwest::{Client, ClientBuilder};
-use std::time::Duration;
+
+use crate::config;
 
 /// Global shared HTTP client instance with optimized settings
 ///
 /// Uses connection pooling and compression for optimal performance.
 /// The client is thread-safe and can be shared across the application.
 pub static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+    let http = &config::get().http;
+
     ClientBuilder::new()
         // Connection pooling settings
         .pool_max_idle_per_host(10)
-        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_idle_timeout(http.pool_idle_timeout)
         // Timeout settings - i-Ma'luum can be slow
-        .connect_timeout(Duration::from_secs(10))
-        .timeout(Duration::from_secs(30))
+        .connect_timeout(http.connect_timeout)
+        .timeout(http.request_timeout)
         // Enable compression
         .gzip(true)
         .brotli(true)
         .deflate(true)
         // TCP settings for better performance
         .tcp_nodelay(true)
-        .tcp_keepalive(Duration::from_secs(60))
+        .tcp_keepalive(http.tcp_keepalive)
         // Redirect policy - follow redirects automatically
         .redirect(reqwest::redirect::Policy::limited(10))
         // Disable HTTP/2 prior knowledge - let negotiation happen naturally
@@ -37,24 +40,27 @@ pub static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
 /// Creates a new HTTP client with cookie jar support
 ///
 /// This client maintains cookies across requests, useful for authenticated sessions.
-/// It uses the same optimized settings as the global client.
+/// It uses the same optimized settings as the global client, with timeouts
+/// taken from the `config` module.
 pub fn create_client_with_cookies() -> Client {
+    let http = &config::get().http;
+
     ClientBuilder::new()
         // Enable cookie store
         .cookie_store(true)
         // Connection pooling settings
         .pool_max_idle_per_host(10)
-        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_idle_timeout(http.pool_idle_timeout)
         // Timeout settings - i-Ma'luum can be slow
-        .connect_timeout(Duration::from_secs(10))
-        .timeout(Duration::from_secs(30))
+        .connect_timeout(http.connect_timeout)
+        .timeout(http.request_timeout)
         // Enable compression
         .gzip(true)
         .brotli(true)
         .deflate(true)
         // TCP settings for better performance
         .tcp_nodelay(true)
-        .tcp_keepalive(Duration::from_secs(60))
+        .tcp_keepalive(http.tcp_keepalive)
         // Redirect policy - follow redirects automatically
         .redirect(reqwest::redirect::Policy::none())
         // Disable HTTP/2 prior knowledge - let negotiation happen naturally
@@ -4,18 +4,28 @@
 //! This service provides optimized HTTP client handling with connection pooling,
 //! cookie management, and efficient async I/O.
 
+pub mod audit;
 pub mod auth;
+pub mod config;
+pub mod health;
 pub mod http;
+pub mod metadata;
+pub mod metrics;
 pub mod middleware;
+pub mod vault;
 
+use crate::audit::AuditLayer;
 use crate::auth::grpc::GRPCServer;
 use crate::auth::grpc::auth_proto::auth_server::AuthServer;
 use crate::middleware::pb::echo_server::EchoServer as EchoService;
 use crate::middleware::{EchoServer, check_auth};
+use crate::vault::grpc::VaultServer;
+use crate::vault::grpc::vault_proto::vault_server::VaultServer as VaultService;
 use console::Style;
 use dotenvy::dotenv;
-use log::{error, info};
+use log::{error, info, warn};
 use std::env;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::Server;
 
 #[tokio::main]
@@ -26,6 +36,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logger
     env_logger::init();
 
+    // Load timeouts and size limits before any client or server is built
+    let config = config::Config::from_env().map_err(|e| {
+        error!("Invalid configuration: {}", e);
+        e
+    })?;
+    let max_message_size = config.server.max_message_size;
+    if config.env.is_test() {
+        warn!(
+            "GAS_ENV=test: live IIUM hosts are blocked, using upstream CAS at {}",
+            config.upstream.cas_root
+        );
+    }
+    config::init(config)?;
+
     // Get bind address from environment or use default
     let addr = env::var("BIND_ADDR")
         .unwrap_or_else(|_| "0.0.0.0:50052".to_string())
@@ -39,18 +63,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let echo_server = EchoServer::default();
 
+    // The service account vault is only enabled when a vault key is configured
+    let vault_server = VaultServer::from_env().map_err(|e| {
+        error!("Failed to create vault server: {}", e);
+        e
+    })?;
+
+    if vault_server.is_none() {
+        info!("GAS_VAULT_KEY not set, service account vault disabled");
+    }
+
     info!("Initializing gRPC services...");
 
-    // Build the gRPC server with both services
-    let auth_service = AuthServer::new(auth_server);
-    let echo_service = EchoService::with_interceptor(echo_server, check_auth);
+    // Build the gRPC server with all services
+    let auth_service = AuthServer::new(auth_server)
+        .max_decoding_message_size(max_message_size)
+        .max_encoding_message_size(max_message_size);
+    let echo_service = InterceptedService::new(
+        EchoService::new(echo_server)
+            .max_decoding_message_size(max_message_size)
+            .max_encoding_message_size(max_message_size),
+        check_auth,
+    );
+    let vault_service = vault_server.map(|server| {
+        VaultService::new(server)
+            .max_decoding_message_size(max_message_size)
+            .max_encoding_message_size(max_message_size)
+    });
+
+    // Health service, kept in sync with the current load
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    tokio::spawn(health::report_load(health_reporter));
 
     print_intro();
 
     // Start the server
     Server::builder()
+        .layer(AuditLayer::default())
+        .add_service(health_service)
         .add_service(auth_service)
         .add_service(echo_service)
+        .add_optional_service(vault_service)
         .serve(addr)
         .await?;
 
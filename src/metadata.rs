@@ -0,0 +1,13 @@
+//! gRPC metadata keys shared across services
+//!
+//! Kept separate from the services that authenticate these values so that
+//! cross-cutting layers such as auditing can read them without depending on
+//! any one service.
+
+/// Metadata key identifying the calling tenant
+pub const TENANT_METADATA_KEY: &str = "x-tenant-id";
+
+/// Metadata key identifying the calling application
+///
+/// This is supplied by the caller and is not authenticated.
+pub const APP_METADATA_KEY: &str = "x-app-id";
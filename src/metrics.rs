@@ -0,0 +1,25 @@
+//! Process-wide counters for upstream behaviour
+//!
+//! Counters are monotonically increasing and are logged to the `metrics`
+//! target every time they change, so they can be collected from the logs.
+
+use log::warn;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Upstream responses that ended before the full body was received
+static UPSTREAM_TRUNCATED_RESPONSES: AtomicU64 = AtomicU64::new(0);
+
+/// CAS session requests retried after a truncated upstream response
+static UPSTREAM_RETRIES: AtomicU64 = AtomicU64::new(0);
+
+/// Records a truncated upstream response
+pub fn record_truncated_response() {
+    let total = UPSTREAM_TRUNCATED_RESPONSES.fetch_add(1, Ordering::Relaxed) + 1;
+    warn!(target: "metrics", "upstream_truncated_responses_total={}", total);
+}
+
+/// Records an automatic retry of the CAS session request
+pub fn record_upstream_retry() {
+    let total = UPSTREAM_RETRIES.fetch_add(1, Ordering::Relaxed) + 1;
+    warn!(target: "metrics", "upstream_retries_total={}", total);
+}
@@ -0,0 +1,45 @@
+//! Error types for the service account credential vault
+//!
+//! This module defines the errors raised while sealing, storing and opening
+//! service account credentials, along with their tonic::Status conversion.
+
+use thiserror::Error;
+use tonic::Status;
+
+/// Custom error types for vault operations
+#[derive(Error, Debug)]
+pub enum VaultError {
+    #[error("Invalid vault key: expected 32 hex-encoded bytes")]
+    InvalidKey,
+
+    #[error("Service account '{name}' not found for tenant '{tenant}'")]
+    AccountNotFound { tenant: String, name: String },
+
+    #[error("Failed to encrypt service account credentials")]
+    EncryptionFailed,
+
+    #[error("Failed to decrypt service account credentials")]
+    DecryptionFailed,
+
+    #[error("Vault storage is unavailable")]
+    StoragePoisoned,
+
+    #[error("Failed to access vault file: {0}")]
+    StorageIo(#[from] std::io::Error),
+
+    #[error("Vault file is corrupt: {0}")]
+    CorruptStorage(String),
+}
+
+/// Convert VaultError to tonic::Status for gRPC responses
+impl From<VaultError> for Status {
+    fn from(error: VaultError) -> Self {
+        match error {
+            VaultError::AccountNotFound { .. } => Status::not_found(error.to_string()),
+            _ => Status::internal(error.to_string()),
+        }
+    }
+}
+
+/// Result type alias for vault operations
+pub type VaultResult<T> = Result<T, VaultError>;
@@ -0,0 +1,385 @@
+//! gRPC service implementation for the service account vault
+//!
+//! This module exposes the CredentialVault over gRPC. Admins store service
+//! accounts and tenant tokens with the vault admin token, and tenants log in
+//! under a named service account with their own token, without ever
+//! receiving the account's password.
+
+use log::{error, info, warn};
+use std::env;
+use subtle::ConstantTimeEq;
+use tonic::{Request, Response, Status, metadata::MetadataMap};
+
+// Import generated protobuf code
+pub mod vault_proto {
+    tonic::include_proto!("grpc.gas.vault");
+}
+
+use vault_proto::vault_server::Vault;
+use vault_proto::{
+    ServiceAccountLoginRequest, ServiceAccountLoginResponse, SetTenantTokenRequest,
+    SetTenantTokenResponse, StoreServiceAccountRequest, StoreServiceAccountResponse,
+};
+
+use crate::auth::service::AuthService;
+use crate::metadata::TENANT_METADATA_KEY;
+use crate::vault::errors::VaultError;
+use crate::vault::service::CredentialVault;
+
+/// Environment variable holding the token required to store service accounts
+pub const VAULT_ADMIN_TOKEN_ENV: &str = "GAS_VAULT_ADMIN_TOKEN";
+
+/// gRPC server implementation for the service account vault
+pub struct VaultServer {
+    vault: CredentialVault,
+    auth_service: AuthService,
+    admin_token: Option<String>,
+}
+
+impl VaultServer {
+    /// Creates a new VaultServer instance
+    ///
+    /// An empty admin token is treated as unset, so it can never be matched
+    /// by an empty bearer token.
+    pub fn new(vault: CredentialVault, admin_token: Option<String>) -> Self {
+        Self {
+            vault,
+            auth_service: AuthService::default(),
+            admin_token: admin_token.filter(|t| !t.trim().is_empty()),
+        }
+    }
+
+    /// Creates a VaultServer from the environment
+    ///
+    /// Returns `Ok(None)` when `GAS_VAULT_KEY` is not set, leaving the vault
+    /// disabled.
+    pub fn from_env() -> Result<Option<Self>, VaultError> {
+        let admin_token = env::var(VAULT_ADMIN_TOKEN_ENV)
+            .ok()
+            .filter(|t| !t.trim().is_empty());
+        Ok(CredentialVault::from_env()?.map(|vault| Self::new(vault, admin_token)))
+    }
+
+    /// Checks the request carries the vault admin bearer token
+    fn check_admin(&self, metadata: &MetadataMap) -> Result<(), Status> {
+        let Some(admin_token) = &self.admin_token else {
+            return Err(Status::internal(
+                "Server misconfiguration: missing vault admin token",
+            ));
+        };
+
+        match bearer_token(metadata) {
+            Some(token) if bool::from(token.as_bytes().ct_eq(admin_token.as_bytes())) => Ok(()),
+            _ => Err(Status::unauthenticated("No valid vault admin token")),
+        }
+    }
+
+    /// Authenticates the calling tenant
+    ///
+    /// The tenant named in the `x-tenant-id` metadata must present its own
+    /// token as `authorization: Bearer <token>`.
+    fn authenticate_tenant(&self, metadata: &MetadataMap) -> Result<String, Status> {
+        let tenant = match metadata.get(TENANT_METADATA_KEY).map(|t| t.to_str()) {
+            Some(Ok(tenant)) if !tenant.is_empty() => tenant.to_string(),
+            _ => return Err(Status::unauthenticated("Missing tenant id")),
+        };
+
+        let token = bearer_token(metadata)
+            .ok_or_else(|| Status::unauthenticated("No valid tenant token"))?;
+
+        if self.vault.verify_tenant_token(&tenant, token)? {
+            Ok(tenant)
+        } else {
+            warn!(
+                "Rejected service account request with invalid token for tenant {}",
+                tenant
+            );
+            Err(Status::unauthenticated("No valid tenant token"))
+        }
+    }
+}
+
+/// Extracts the bearer token from the `authorization` metadata
+///
+/// Returns `None` for an empty token.
+fn bearer_token(metadata: &MetadataMap) -> Option<&str> {
+    metadata
+        .get("authorization")
+        .and_then(|t| t.to_str().ok())
+        .and_then(|t| t.strip_prefix("Bearer "))
+        .filter(|t| !t.trim().is_empty())
+}
+
+#[tonic::async_trait]
+impl Vault for VaultServer {
+    /// Stores a service account for a tenant
+    ///
+    /// # Arguments
+    /// * `request` - gRPC request containing the tenant, account name and credentials
+    ///
+    /// # Returns
+    /// * `Ok(Response<StoreServiceAccountResponse>)` - The account was sealed and stored
+    /// * `Err(Status)` - The caller is not an admin or the request is invalid
+    async fn store_service_account(
+        &self,
+        request: Request<StoreServiceAccountRequest>,
+    ) -> Result<Response<StoreServiceAccountResponse>, Status> {
+        self.check_admin(request.metadata())?;
+        let req = request.into_inner();
+
+        if req.tenant.is_empty() || req.name.is_empty() {
+            return Err(Status::invalid_argument(
+                "Tenant and account name cannot be empty",
+            ));
+        }
+
+        if req.username.is_empty() || req.password.is_empty() {
+            return Err(Status::invalid_argument(
+                "Username and password cannot be empty",
+            ));
+        }
+
+        self.vault
+            .store(&req.tenant, &req.name, &req.username, &req.password)
+            .map_err(|e| {
+                error!(
+                    "Failed to store service account {}/{}: {:?}",
+                    req.tenant, req.name, e
+                );
+                Status::from(e)
+            })?;
+
+        info!("Stored service account {}/{}", req.tenant, req.name);
+
+        Ok(Response::new(StoreServiceAccountResponse {
+            tenant: req.tenant,
+            name: req.name,
+        }))
+    }
+
+    /// Sets the token a tenant authenticates with
+    ///
+    /// # Arguments
+    /// * `request` - gRPC request containing the tenant and its new token
+    ///
+    /// # Returns
+    /// * `Ok(Response<SetTenantTokenResponse>)` - The token was sealed and stored
+    /// * `Err(Status)` - The caller is not an admin or the request is invalid
+    async fn set_tenant_token(
+        &self,
+        request: Request<SetTenantTokenRequest>,
+    ) -> Result<Response<SetTenantTokenResponse>, Status> {
+        self.check_admin(request.metadata())?;
+        let req = request.into_inner();
+
+        if req.tenant.is_empty() || req.token.is_empty() {
+            return Err(Status::invalid_argument("Tenant and token cannot be empty"));
+        }
+
+        self.vault
+            .set_tenant_token(&req.tenant, &req.token)
+            .map_err(|e| {
+                error!("Failed to set token for tenant {}: {:?}", req.tenant, e);
+                Status::from(e)
+            })?;
+
+        info!("Set token for tenant {}", req.tenant);
+
+        Ok(Response::new(SetTenantTokenResponse { tenant: req.tenant }))
+    }
+
+    /// Logs in under one of the calling tenant's service accounts
+    ///
+    /// # Arguments
+    /// * `request` - gRPC request naming the service account, with the tenant and its token in metadata
+    ///
+    /// # Returns
+    /// * `Ok(Response<ServiceAccountLoginResponse>)` - Successful authentication with token
+    /// * `Err(Status)` - Unknown account, authentication failed or error occurred
+    async fn service_account_login(
+        &self,
+        request: Request<ServiceAccountLoginRequest>,
+    ) -> Result<Response<ServiceAccountLoginResponse>, Status> {
+        let tenant = self.authenticate_tenant(request.metadata())?;
+        let req = request.into_inner();
+
+        if req.name.is_empty() {
+            return Err(Status::invalid_argument("Account name cannot be empty"));
+        }
+
+        info!(
+            "Service account login requested for {}/{}",
+            tenant, req.name
+        );
+
+        let (username, password) = self.vault.open(&tenant, &req.name).map_err(|e| {
+            error!(
+                "Failed to open service account {}/{}: {:?}",
+                tenant, req.name, e
+            );
+            Status::from(e)
+        })?;
+
+        match self.auth_service.login(username, password).await {
+            Ok((token, username, _)) => Ok(Response::new(ServiceAccountLoginResponse {
+                token,
+                username,
+            })),
+            Err(e) => {
+                error!(
+                    "Service account login failed for {}/{}: {:?}",
+                    tenant, req.name, e
+                );
+                Err(Status::from(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_server() -> VaultServer {
+        let vault = CredentialVault::new(&[7; 32]).unwrap();
+        VaultServer::new(vault, Some("admin".to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_store_requires_admin_token() {
+        let server = test_server();
+        let request = Request::new(StoreServiceAccountRequest {
+            tenant: "kict".to_string(),
+            name: "reader".to_string(),
+            username: "svc_reader".to_string(),
+            password: "s3cret".to_string(),
+        });
+
+        let result = server.store_service_account(request).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_empty_admin_token_is_unset() {
+        let vault = CredentialVault::new(&[7; 32]).unwrap();
+        let server = VaultServer::new(vault, Some(" ".to_string()));
+        let mut request = Request::new(SetTenantTokenRequest {
+            tenant: "kict".to_string(),
+            token: "kict-token".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer  ".parse().unwrap());
+
+        let result = server.set_tenant_token(request).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Internal);
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_empty_bearer_token() {
+        let server = test_server();
+        let mut request = Request::new(StoreServiceAccountRequest {
+            tenant: "kict".to_string(),
+            name: "reader".to_string(),
+            username: "svc_reader".to_string(),
+            password: "s3cret".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer ".parse().unwrap());
+
+        let result = server.store_service_account(request).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_store_with_admin_token() {
+        let server = test_server();
+        let mut request = Request::new(StoreServiceAccountRequest {
+            tenant: "kict".to_string(),
+            name: "reader".to_string(),
+            username: "svc_reader".to_string(),
+            password: "s3cret".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer admin".parse().unwrap());
+
+        assert!(server.store_service_account(request).await.is_ok());
+        assert!(server.vault.open("kict", "reader").is_ok());
+    }
+
+    fn login_request(tenant: &str, token: Option<&str>) -> Request<ServiceAccountLoginRequest> {
+        let mut request = Request::new(ServiceAccountLoginRequest {
+            name: "reader".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert(TENANT_METADATA_KEY, tenant.parse().unwrap());
+        if let Some(token) = token {
+            request.metadata_mut().insert(
+                "authorization",
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_login_requires_tenant() {
+        let server = test_server();
+        let request = Request::new(ServiceAccountLoginRequest {
+            name: "reader".to_string(),
+        });
+
+        let result = server.service_account_login(request).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_spoofed_tenant() {
+        let server = test_server();
+        server
+            .vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+        server.vault.set_tenant_token("kict", "kict-token").unwrap();
+        server
+            .vault
+            .set_tenant_token("kenms", "kenms-token")
+            .unwrap();
+
+        for token in [None, Some("guess"), Some("kenms-token")] {
+            let result = server
+                .service_account_login(login_request("kict", token))
+                .await;
+            assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_unknown_account() {
+        let server = test_server();
+        server.vault.set_tenant_token("kict", "kict-token").unwrap();
+
+        let result = server
+            .service_account_login(login_request("kict", Some("kict-token")))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_set_tenant_token_requires_admin_token() {
+        let server = test_server();
+        let mut request = Request::new(SetTenantTokenRequest {
+            tenant: "kict".to_string(),
+            token: "kict-token".to_string(),
+        });
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer kict-token".parse().unwrap());
+
+        let result = server.set_tenant_token(request).await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+}
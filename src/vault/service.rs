@@ -0,0 +1,604 @@
+//! Encrypted credential vault for tenant service accounts
+//!
+//! Service account passwords and tenant tokens are sealed with AES-256-GCM as
+//! soon as they are stored and only decrypted when they are needed. The
+//! tenant, account name and username are bound to each ciphertext as
+//! associated data, so a sealed secret cannot be replayed under a different
+//! tenant or account.
+//!
+//! When `GAS_VAULT_PATH` is set the sealed records are also written to that
+//! file, so they survive restarts. Every lookup reloads the file if its
+//! contents changed, which lets replicas sharing the file see each other's
+//! writes. Writers hold an exclusive lock on a `.lock` file next to it while
+//! they reload, update and replace the file, so concurrent writes from
+//! several replicas are never lost. Both files are readable by their owner
+//! only.
+
+use aes_gcm::{
+    Aes256Gcm,
+    aead::{Aead, AeadCore, KeyInit, Nonce, OsRng, Payload},
+};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use subtle::ConstantTimeEq;
+
+use crate::vault::errors::*;
+
+/// Environment variable holding the hex-encoded 256-bit vault key
+pub const VAULT_KEY_ENV: &str = "GAS_VAULT_KEY";
+
+/// Environment variable holding the path of the vault file
+pub const VAULT_PATH_ENV: &str = "GAS_VAULT_PATH";
+
+/// First line of every vault file
+const VAULT_FILE_HEADER: &str = "gas-vault v1";
+
+/// Distinguishes the temporary files of writers within one process
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A secret sealed with the vault key
+#[derive(Clone)]
+struct Sealed {
+    nonce: Nonce<Aes256Gcm>,
+    ciphertext: Vec<u8>,
+}
+
+/// A service account with its sealed password
+#[derive(Clone)]
+struct SealedAccount {
+    username: String,
+    password: Sealed,
+}
+
+/// All sealed records held by the vault
+#[derive(Clone, Default)]
+struct Records {
+    accounts: HashMap<(String, String), SealedAccount>,
+    tenant_tokens: HashMap<String, Sealed>,
+}
+
+/// Records plus the vault file contents they were loaded from
+#[derive(Default)]
+struct State {
+    records: Records,
+    contents: Option<String>,
+}
+
+/// Vault of encrypted service account credentials and tenant tokens, keyed by
+/// tenant and account name
+pub struct CredentialVault {
+    cipher: Aes256Gcm,
+    path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl CredentialVault {
+    /// Creates a new in-memory vault from a raw 32-byte key
+    pub fn new(key: &[u8]) -> VaultResult<Self> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| VaultError::InvalidKey)?;
+
+        Ok(Self {
+            cipher,
+            path: None,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Creates a vault persisted to `path`, loading any records already there
+    pub fn with_path(key: &[u8], path: impl Into<PathBuf>) -> VaultResult<Self> {
+        let vault = Self {
+            path: Some(path.into()),
+            ..Self::new(key)?
+        };
+        // Load the file now so an unreadable vault fails at startup
+        drop(vault.lock()?);
+        Ok(vault)
+    }
+
+    /// Creates a vault from the `GAS_VAULT_KEY` and `GAS_VAULT_PATH`
+    /// environment variables
+    ///
+    /// # Returns
+    /// * `Ok(Some(vault))` - The key is set and valid
+    /// * `Ok(None)` - The key is not set; the vault is disabled
+    /// * `Err(VaultError)` - The key is malformed or the vault file is unreadable
+    pub fn from_env() -> VaultResult<Option<Self>> {
+        let Ok(hex_key) = env::var(VAULT_KEY_ENV) else {
+            return Ok(None);
+        };
+        let key = hex::decode(hex_key.trim()).map_err(|_| VaultError::InvalidKey)?;
+
+        match env::var(VAULT_PATH_ENV) {
+            Ok(path) => Self::with_path(&key, path).map(Some),
+            Err(_) => Self::new(&key).map(Some),
+        }
+    }
+
+    /// Encrypts and stores a service account, replacing any existing account
+    /// with the same name for the tenant
+    pub fn store(
+        &self,
+        tenant: &str,
+        name: &str,
+        username: &str,
+        password: &str,
+    ) -> VaultResult<()> {
+        let account = SealedAccount {
+            username: username.to_string(),
+            password: self.seal(password, &account_aad(tenant, name, username))?,
+        };
+
+        self.update(|records| {
+            records
+                .accounts
+                .insert((tenant.to_string(), name.to_string()), account);
+        })
+    }
+
+    /// Encrypts and stores the token a tenant authenticates with, replacing
+    /// any previous token
+    pub fn set_tenant_token(&self, tenant: &str, token: &str) -> VaultResult<()> {
+        let sealed = self.seal(token, &tenant_token_aad(tenant))?;
+
+        self.update(|records| {
+            records.tenant_tokens.insert(tenant.to_string(), sealed);
+        })
+    }
+
+    /// Checks `token` against the tenant's stored token in constant time
+    ///
+    /// Returns `Ok(false)` for unknown tenants.
+    pub fn verify_tenant_token(&self, tenant: &str, token: &str) -> VaultResult<bool> {
+        let state = self.lock()?;
+        let Some(sealed) = state.records.tenant_tokens.get(tenant) else {
+            return Ok(false);
+        };
+
+        let expected = self.unseal(sealed, &tenant_token_aad(tenant))?;
+        Ok(expected.as_bytes().ct_eq(token.as_bytes()).into())
+    }
+
+    /// Decrypts a tenant's service account
+    ///
+    /// # Returns
+    /// * `Ok((username, password))` - The decrypted credentials
+    /// * `Err(VaultError)` - The account does not exist or cannot be decrypted
+    pub fn open(&self, tenant: &str, name: &str) -> VaultResult<(String, String)> {
+        let state = self.lock()?;
+        let account = state
+            .records
+            .accounts
+            .get(&(tenant.to_string(), name.to_string()))
+            .ok_or_else(|| VaultError::AccountNotFound {
+                tenant: tenant.to_string(),
+                name: name.to_string(),
+            })?;
+
+        let aad = account_aad(tenant, name, &account.username);
+        let password = self.unseal(&account.password, &aad)?;
+        Ok((account.username.clone(), password))
+    }
+
+    /// Locks the vault state, first reloading the vault file if it changed
+    fn lock(&self) -> VaultResult<MutexGuard<'_, State>> {
+        let mut state = self.state.lock().map_err(|_| VaultError::StoragePoisoned)?;
+        self.reload(&mut state)?;
+        Ok(state)
+    }
+
+    /// Applies `change` to the records and persists them
+    ///
+    /// With a vault file, the file lock is held from reloading the records
+    /// until the updated file is in place, so a concurrent write by another
+    /// replica is never overwritten with stale records.
+    fn update(&self, change: impl FnOnce(&mut Records)) -> VaultResult<()> {
+        let mut state = self.state.lock().map_err(|_| VaultError::StoragePoisoned)?;
+
+        let Some(path) = &self.path else {
+            change(&mut state.records);
+            return Ok(());
+        };
+
+        let lock_file = private_file_options()
+            .write(true)
+            .truncate(false)
+            .open(with_suffix(path, ".lock"))?;
+        lock_file.lock()?;
+
+        // The change is applied to a copy, so a failed write leaves the
+        // state matching the file
+        self.reload(&mut state)?;
+        let mut records = state.records.clone();
+        change(&mut records);
+        let contents = format_records(&records);
+        write_file(path, &contents)?;
+
+        *state = State {
+            records,
+            contents: Some(contents),
+        };
+        Ok(())
+    }
+
+    /// Reloads the records if the vault file's contents changed
+    fn reload(&self, state: &mut State) -> VaultResult<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        if contents != state.contents {
+            state.records = match &contents {
+                Some(contents) => parse_records(contents)?,
+                None => Records::default(),
+            };
+            state.contents = contents;
+        }
+
+        Ok(())
+    }
+
+    /// Encrypts a secret bound to `aad`
+    fn seal(&self, secret: &str, aad: &str) -> VaultResult<Sealed> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: secret.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| VaultError::EncryptionFailed)?;
+
+        Ok(Sealed { nonce, ciphertext })
+    }
+
+    /// Decrypts a secret bound to `aad`
+    fn unseal(&self, sealed: &Sealed, aad: &str) -> VaultResult<String> {
+        let plaintext = self
+            .cipher
+            .decrypt(
+                &sealed.nonce,
+                Payload {
+                    msg: &sealed.ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map_err(|_| VaultError::DecryptionFailed)?;
+
+        String::from_utf8(plaintext).map_err(|_| VaultError::DecryptionFailed)
+    }
+}
+
+/// Builds the associated data binding a password to its tenant and account
+#[inline]
+fn account_aad(tenant: &str, name: &str, username: &str) -> String {
+    format!("account\0{}\0{}\0{}", tenant, name, username)
+}
+
+/// Builds the associated data binding a token to its tenant
+#[inline]
+fn tenant_token_aad(tenant: &str) -> String {
+    format!("tenant-token\0{}", tenant)
+}
+
+/// Parses the contents of a vault file
+///
+/// Each record is one line of space-separated, hex-encoded fields:
+/// `account <tenant> <name> <username> <nonce> <ciphertext>` or
+/// `tenant <tenant> <nonce> <ciphertext>`.
+fn parse_records(contents: &str) -> VaultResult<Records> {
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(VAULT_FILE_HEADER) {
+        return Err(VaultError::CorruptStorage("missing header".to_string()));
+    }
+
+    let mut records = Records::default();
+    for (number, line) in lines.enumerate().filter(|(_, l)| !l.is_empty()) {
+        let corrupt =
+            || VaultError::CorruptStorage(format!("invalid record on line {}", number + 2));
+        let mut fields = line.split(' ');
+        let kind = fields.next().ok_or_else(corrupt)?;
+        let fields = fields
+            .map(hex::decode)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| corrupt())?;
+
+        let text = |bytes: &[u8]| String::from_utf8(bytes.to_vec()).map_err(|_| corrupt());
+        let sealed = |nonce: &[u8], ciphertext: &[u8]| {
+            let nonce: [u8; 12] = nonce.try_into().map_err(|_| corrupt())?;
+            Ok::<_, VaultError>(Sealed {
+                nonce: nonce.into(),
+                ciphertext: ciphertext.to_vec(),
+            })
+        };
+
+        match (kind, fields.as_slice()) {
+            ("account", [tenant, name, username, nonce, ciphertext]) => {
+                records.accounts.insert(
+                    (text(tenant)?, text(name)?),
+                    SealedAccount {
+                        username: text(username)?,
+                        password: sealed(nonce, ciphertext)?,
+                    },
+                );
+            }
+            ("tenant", [tenant, nonce, ciphertext]) => {
+                records
+                    .tenant_tokens
+                    .insert(text(tenant)?, sealed(nonce, ciphertext)?);
+            }
+            _ => return Err(corrupt()),
+        }
+    }
+
+    Ok(records)
+}
+
+/// Formats the records as the contents of a vault file
+fn format_records(records: &Records) -> String {
+    let mut contents = format!("{}\n", VAULT_FILE_HEADER);
+
+    for ((tenant, name), account) in &records.accounts {
+        contents.push_str(&format!(
+            "account {} {} {} {} {}\n",
+            hex::encode(tenant),
+            hex::encode(name),
+            hex::encode(&account.username),
+            hex::encode(account.password.nonce),
+            hex::encode(&account.password.ciphertext),
+        ));
+    }
+
+    for (tenant, token) in &records.tenant_tokens {
+        contents.push_str(&format!(
+            "tenant {} {} {}\n",
+            hex::encode(tenant),
+            hex::encode(token.nonce),
+            hex::encode(&token.ciphertext),
+        ));
+    }
+
+    contents
+}
+
+/// Replaces a vault file atomically
+///
+/// Each writer uses its own temporary file, so concurrent writers cannot
+/// interleave their contents.
+fn write_file(path: &Path, contents: &str) -> VaultResult<()> {
+    let tmp_path = with_suffix(
+        path,
+        &format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+
+    let result = write_new_file(&tmp_path, contents).and_then(|()| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+/// Writes and syncs a new file readable by its owner only
+fn write_new_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut file: File = private_file_options()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()
+}
+
+/// Options creating files readable and writable by their owner only
+fn private_file_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+/// Appends `suffix` to the file name of `path`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KEY: [u8; 32] = [7; 32];
+
+    /// Returns a vault file path unique to the calling test
+    fn temp_vault_path(test: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("gas-vault-{}-{}", std::process::id(), test));
+        remove_vault_files(&path);
+        path
+    }
+
+    /// Removes a vault file and its lock file
+    fn remove_vault_files(path: &Path) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(with_suffix(path, ".lock"));
+    }
+
+    #[test]
+    fn test_vault_rejects_short_key() {
+        assert!(matches!(
+            CredentialVault::new(&[0; 16]),
+            Err(VaultError::InvalidKey)
+        ));
+    }
+
+    #[test]
+    fn test_store_and_open_round_trip() {
+        let vault = CredentialVault::new(&TEST_KEY).unwrap();
+        vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+
+        let (username, password) = vault.open("kict", "reader").unwrap();
+        assert_eq!(username, "svc_reader");
+        assert_eq!(password, "s3cret");
+    }
+
+    #[test]
+    fn test_open_is_tenant_scoped() {
+        let vault = CredentialVault::new(&TEST_KEY).unwrap();
+        vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+
+        assert!(matches!(
+            vault.open("kenms", "reader"),
+            Err(VaultError::AccountNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_password_is_not_stored_in_plaintext() {
+        let vault = CredentialVault::new(&TEST_KEY).unwrap();
+        vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+
+        let state = vault.state.lock().unwrap();
+        let account = state
+            .records
+            .accounts
+            .get(&("kict".to_string(), "reader".to_string()))
+            .unwrap();
+        assert_ne!(account.password.ciphertext, b"s3cret");
+    }
+
+    #[test]
+    fn test_verify_tenant_token() {
+        let vault = CredentialVault::new(&TEST_KEY).unwrap();
+        vault.set_tenant_token("kict", "kict-token").unwrap();
+
+        assert!(vault.verify_tenant_token("kict", "kict-token").unwrap());
+        assert!(!vault.verify_tenant_token("kict", "guess").unwrap());
+        assert!(!vault.verify_tenant_token("kenms", "kict-token").unwrap());
+    }
+
+    #[test]
+    fn test_vault_file_survives_restart() {
+        let path = temp_vault_path("restart");
+
+        let vault = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+        vault.set_tenant_token("kict", "kict-token").unwrap();
+        drop(vault);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&hex::encode("s3cret")));
+
+        let vault = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        assert_eq!(
+            vault.open("kict", "reader").unwrap(),
+            ("svc_reader".to_string(), "s3cret".to_string())
+        );
+        assert!(vault.verify_tenant_token("kict", "kict-token").unwrap());
+
+        remove_vault_files(&path);
+    }
+
+    #[test]
+    fn test_vault_file_shared_between_replicas() {
+        let path = temp_vault_path("replicas");
+        let first = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        let second = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+
+        first
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+        assert!(second.open("kict", "reader").is_ok());
+
+        remove_vault_files(&path);
+    }
+
+    #[test]
+    fn test_vault_file_rejects_wrong_key() {
+        let path = temp_vault_path("wrong-key");
+        let vault = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        vault
+            .store("kict", "reader", "svc_reader", "s3cret")
+            .unwrap();
+
+        let other = CredentialVault::with_path(&[8; 32], &path).unwrap();
+        assert!(matches!(
+            other.open("kict", "reader"),
+            Err(VaultError::DecryptionFailed)
+        ));
+
+        remove_vault_files(&path);
+    }
+
+    #[test]
+    fn test_vault_file_concurrent_writers() {
+        let path = temp_vault_path("concurrent");
+
+        std::thread::scope(|scope| {
+            for replica in 0..4 {
+                let path = &path;
+                scope.spawn(move || {
+                    let vault = CredentialVault::with_path(&TEST_KEY, path).unwrap();
+                    for account in 0..10 {
+                        let name = format!("reader-{}-{}", replica, account);
+                        vault.store("kict", &name, "svc_reader", "s3cret").unwrap();
+                    }
+                });
+            }
+        });
+
+        // No replica overwrote another's accounts with stale records
+        let vault = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        for replica in 0..4 {
+            for account in 0..10 {
+                let name = format!("reader-{}-{}", replica, account);
+                assert!(vault.open("kict", &name).is_ok());
+            }
+        }
+
+        remove_vault_files(&path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_vault_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_vault_path("private");
+        let vault = CredentialVault::with_path(&TEST_KEY, &path).unwrap();
+        vault.set_tenant_token("kict", "kict-token").unwrap();
+
+        for file in [path.clone(), with_suffix(&path, ".lock")] {
+            let mode = fs::metadata(&file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        remove_vault_files(&path);
+    }
+}